@@ -8,33 +8,71 @@ use {
     },
 };
 
+// Conversion factor between micro-lamports, in which `priority` is denominated, and lamports,
+// in which fees are paid out.
+const MICRO_LAMPORTS_PER_LAMPORT: u128 = 1_000_000;
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct TransactionPriorityDetails {
     pub priority: u64,
     pub compute_unit_limit: u64,
+    pub loaded_accounts_data_size_limit: u32,
+}
+
+impl TransactionPriorityDetails {
+    /// `priority` (micro-lamports per compute unit) multiplied by `compute_unit_limit`, without
+    /// scaling down to lamports. Saturates at `u128::MAX` rather than overflowing when the
+    /// product of `priority` and `compute_unit_limit` is large.
+    fn micro_lamport_fee(&self) -> u128 {
+        (self.priority as u128).saturating_mul(self.compute_unit_limit as u128)
+    }
+
+    /// Total prioritization fee, in lamports, this transaction pays the leader: the
+    /// micro-lamport fee scaled down to lamports. Saturates at `u64::MAX` rather than
+    /// overflowing when the product of `priority` and `compute_unit_limit` is large.
+    pub fn total_prioritization_fee(&self, round_compute_unit_price_enabled: bool) -> u64 {
+        let micro_lamport_fee = self.micro_lamport_fee();
+        let lamport_fee = if round_compute_unit_price_enabled {
+            micro_lamport_fee.saturating_add(MICRO_LAMPORTS_PER_LAMPORT - 1)
+        } else {
+            micro_lamport_fee
+        } / MICRO_LAMPORTS_PER_LAMPORT;
+
+        u64::try_from(lamport_fee).unwrap_or(u64::MAX)
+    }
+
+    /// Effective fee paid per compute unit, in micro-lamports, i.e. the micro-lamport
+    /// prioritization fee divided by `compute_unit_limit`. Unlike `total_prioritization_fee`,
+    /// this is not scaled down to lamports, so it doesn't collapse to `0` for the common case of
+    /// a sub-lamport total fee. Returns `0` for transactions that don't consume any compute
+    /// units.
+    pub fn effective_fee_per_compute_unit(&self) -> u64 {
+        if self.compute_unit_limit == 0 {
+            return 0;
+        }
+        u64::try_from(self.micro_lamport_fee() / self.compute_unit_limit as u128)
+            .unwrap_or(u64::MAX)
+    }
 }
 
 pub trait GetTransactionPriorityDetails {
     fn get_transaction_priority_details(
         &self,
+        feature_set: &FeatureSet,
         round_compute_unit_price_enabled: bool,
     ) -> Option<TransactionPriorityDetails>;
 
     fn process_compute_budget_instruction<'a>(
         instructions: impl Iterator<Item = (&'a Pubkey, &'a CompiledInstruction)>,
+        feature_set: &FeatureSet,
         _round_compute_unit_price_enabled: bool,
     ) -> Option<TransactionPriorityDetails> {
-        let mut feature_set = FeatureSet::default();
-        feature_set.activate(
-            &solana_sdk::feature_set::add_set_tx_loaded_accounts_data_size_instruction::id(),
-            0,
-        );
-
         let compute_budget_limits =
-            process_compute_budget_instructions(instructions, &feature_set).ok()?;
+            process_compute_budget_instructions(instructions, feature_set).ok()?;
         Some(TransactionPriorityDetails {
             priority: compute_budget_limits.compute_unit_price,
             compute_unit_limit: u64::from(compute_budget_limits.compute_unit_limit),
+            loaded_accounts_data_size_limit: compute_budget_limits.loaded_accounts_bytes,
         })
     }
 }
@@ -42,10 +80,12 @@ pub trait GetTransactionPriorityDetails {
 impl GetTransactionPriorityDetails for SanitizedVersionedTransaction {
     fn get_transaction_priority_details(
         &self,
+        feature_set: &FeatureSet,
         round_compute_unit_price_enabled: bool,
     ) -> Option<TransactionPriorityDetails> {
         Self::process_compute_budget_instruction(
             self.get_message().program_instructions_iter(),
+            feature_set,
             round_compute_unit_price_enabled,
         )
     }
@@ -54,10 +94,12 @@ impl GetTransactionPriorityDetails for SanitizedVersionedTransaction {
 impl GetTransactionPriorityDetails for SanitizedTransaction {
     fn get_transaction_priority_details(
         &self,
+        feature_set: &FeatureSet,
         round_compute_unit_price_enabled: bool,
     ) -> Option<TransactionPriorityDetails> {
         Self::process_compute_budget_instruction(
             self.message().program_instructions_iter(),
+            feature_set,
             round_compute_unit_price_enabled,
         )
     }
@@ -77,6 +119,11 @@ mod tests {
         },
     };
 
+    // Default `loaded_accounts_data_size_limit` when a transaction doesn't request one via
+    // `ComputeBudgetInstruction::set_loaded_accounts_data_size_limit`.
+    const DEFAULT_LOADED_ACCOUNTS_DATA_SIZE_LIMIT: u32 =
+        solana_program_runtime::compute_budget_processor::MAX_LOADED_ACCOUNTS_DATA_SIZE_BYTES;
+
     #[test]
     fn test_get_priority_with_valid_request_heap_frame_tx() {
         let keypair = Keypair::new();
@@ -93,12 +140,14 @@ mod tests {
         let sanitized_versioned_transaction =
             SanitizedVersionedTransaction::try_new(versioned_transaction).unwrap();
         assert_eq!(
-            sanitized_versioned_transaction.get_transaction_priority_details(false),
+            sanitized_versioned_transaction
+                .get_transaction_priority_details(&FeatureSet::all_enabled(), false),
             Some(TransactionPriorityDetails {
                 priority: 0,
                 compute_unit_limit:
                     solana_program_runtime::compute_budget_processor::DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT
                     as u64,
+                loaded_accounts_data_size_limit: DEFAULT_LOADED_ACCOUNTS_DATA_SIZE_LIMIT,
             })
         );
 
@@ -106,12 +155,13 @@ mod tests {
         let sanitized_transaction =
             SanitizedTransaction::try_from_legacy_transaction(transaction).unwrap();
         assert_eq!(
-            sanitized_transaction.get_transaction_priority_details(false),
+            sanitized_transaction.get_transaction_priority_details(&FeatureSet::all_enabled(), false),
             Some(TransactionPriorityDetails {
                 priority: 0,
                 compute_unit_limit:
                     solana_program_runtime::compute_budget_processor::DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT
                     as u64,
+                loaded_accounts_data_size_limit: DEFAULT_LOADED_ACCOUNTS_DATA_SIZE_LIMIT,
             })
         );
     }
@@ -133,10 +183,12 @@ mod tests {
         let sanitized_versioned_transaction =
             SanitizedVersionedTransaction::try_new(versioned_transaction).unwrap();
         assert_eq!(
-            sanitized_versioned_transaction.get_transaction_priority_details(false),
+            sanitized_versioned_transaction
+                .get_transaction_priority_details(&FeatureSet::all_enabled(), false),
             Some(TransactionPriorityDetails {
                 priority: 0,
                 compute_unit_limit: requested_cu as u64,
+                loaded_accounts_data_size_limit: DEFAULT_LOADED_ACCOUNTS_DATA_SIZE_LIMIT,
             })
         );
 
@@ -144,10 +196,11 @@ mod tests {
         let sanitized_transaction =
             SanitizedTransaction::try_from_legacy_transaction(transaction).unwrap();
         assert_eq!(
-            sanitized_transaction.get_transaction_priority_details(false),
+            sanitized_transaction.get_transaction_priority_details(&FeatureSet::all_enabled(), false),
             Some(TransactionPriorityDetails {
                 priority: 0,
                 compute_unit_limit: requested_cu as u64,
+                loaded_accounts_data_size_limit: DEFAULT_LOADED_ACCOUNTS_DATA_SIZE_LIMIT,
             })
         );
     }
@@ -169,12 +222,14 @@ mod tests {
         let sanitized_versioned_transaction =
             SanitizedVersionedTransaction::try_new(versioned_transaction).unwrap();
         assert_eq!(
-            sanitized_versioned_transaction.get_transaction_priority_details(false),
+            sanitized_versioned_transaction
+                .get_transaction_priority_details(&FeatureSet::all_enabled(), false),
             Some(TransactionPriorityDetails {
                 priority: requested_price,
                 compute_unit_limit:
                     solana_program_runtime::compute_budget_processor::DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT
                     as u64,
+                loaded_accounts_data_size_limit: DEFAULT_LOADED_ACCOUNTS_DATA_SIZE_LIMIT,
             })
         );
 
@@ -182,13 +237,106 @@ mod tests {
         let sanitized_transaction =
             SanitizedTransaction::try_from_legacy_transaction(transaction).unwrap();
         assert_eq!(
-            sanitized_transaction.get_transaction_priority_details(false),
+            sanitized_transaction.get_transaction_priority_details(&FeatureSet::all_enabled(), false),
             Some(TransactionPriorityDetails {
                 priority: requested_price,
                 compute_unit_limit:
                     solana_program_runtime::compute_budget_processor::DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT
                     as u64,
+                loaded_accounts_data_size_limit: DEFAULT_LOADED_ACCOUNTS_DATA_SIZE_LIMIT,
             })
         );
     }
+
+    #[test]
+    fn test_get_priority_with_valid_set_loaded_accounts_data_size_limit() {
+        let requested_limit = 32 * 1024;
+        let keypair = Keypair::new();
+        let transaction = Transaction::new_unsigned(Message::new(
+            &[
+                system_instruction::transfer(&keypair.pubkey(), &Pubkey::new_unique(), 1),
+                ComputeBudgetInstruction::set_loaded_accounts_data_size_limit(requested_limit),
+            ],
+            Some(&keypair.pubkey()),
+        ));
+
+        // assert for SanitizedVersionedTransaction
+        let versioned_transaction = VersionedTransaction::from(transaction.clone());
+        let sanitized_versioned_transaction =
+            SanitizedVersionedTransaction::try_new(versioned_transaction).unwrap();
+        assert_eq!(
+            sanitized_versioned_transaction
+                .get_transaction_priority_details(&FeatureSet::all_enabled(), false),
+            Some(TransactionPriorityDetails {
+                priority: 0,
+                compute_unit_limit:
+                    solana_program_runtime::compute_budget_processor::DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT
+                    as u64,
+                loaded_accounts_data_size_limit: requested_limit,
+            })
+        );
+
+        // assert for SanitizedTransaction
+        let sanitized_transaction =
+            SanitizedTransaction::try_from_legacy_transaction(transaction).unwrap();
+        assert_eq!(
+            sanitized_transaction.get_transaction_priority_details(&FeatureSet::all_enabled(), false),
+            Some(TransactionPriorityDetails {
+                priority: 0,
+                compute_unit_limit:
+                    solana_program_runtime::compute_budget_processor::DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT
+                    as u64,
+                loaded_accounts_data_size_limit: requested_limit,
+            })
+        );
+    }
+
+    #[test]
+    fn test_total_prioritization_fee_saturates_at_u64_max() {
+        let details = TransactionPriorityDetails {
+            priority: u64::MAX,
+            compute_unit_limit: u64::MAX,
+            loaded_accounts_data_size_limit: DEFAULT_LOADED_ACCOUNTS_DATA_SIZE_LIMIT,
+        };
+        assert_eq!(details.total_prioritization_fee(false), u64::MAX);
+        assert_eq!(details.total_prioritization_fee(true), u64::MAX);
+        assert_eq!(details.effective_fee_per_compute_unit(), u64::MAX);
+    }
+
+    #[test]
+    fn test_total_prioritization_fee_rounding() {
+        // priority * compute_unit_limit = 1_500_000 micro-lamports, i.e. 1.5 lamports.
+        let details = TransactionPriorityDetails {
+            priority: 1_500,
+            compute_unit_limit: 1_000,
+            loaded_accounts_data_size_limit: DEFAULT_LOADED_ACCOUNTS_DATA_SIZE_LIMIT,
+        };
+        assert_eq!(details.total_prioritization_fee(false), 1);
+        assert_eq!(details.total_prioritization_fee(true), 2);
+    }
+
+    #[test]
+    fn test_effective_fee_per_compute_unit_sub_lamport_total() {
+        // The total prioritization fee truncates to 0 lamports, but the effective per-CU fee
+        // should still reflect the requested price instead of collapsing to 0.
+        // priority * compute_unit_limit = 500_000 micro-lamports, i.e. 0.5 lamports.
+        let details = TransactionPriorityDetails {
+            priority: 1_000,
+            compute_unit_limit: 500,
+            loaded_accounts_data_size_limit: DEFAULT_LOADED_ACCOUNTS_DATA_SIZE_LIMIT,
+        };
+        assert_eq!(details.total_prioritization_fee(false), 0);
+        assert_eq!(details.effective_fee_per_compute_unit(), 1_000);
+    }
+
+    #[test]
+    fn test_total_prioritization_fee_zero_compute_unit_limit() {
+        let details = TransactionPriorityDetails {
+            priority: 1_000,
+            compute_unit_limit: 0,
+            loaded_accounts_data_size_limit: DEFAULT_LOADED_ACCOUNTS_DATA_SIZE_LIMIT,
+        };
+        assert_eq!(details.total_prioritization_fee(false), 0);
+        assert_eq!(details.effective_fee_per_compute_unit(), 0);
+    }
 }